@@ -0,0 +1,39 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+	/// No supported Legion keyboard was present on the HID bus.
+	DeviceNotFound,
+	/// The requested lighting state failed range validation in
+	/// `build_payload` (speed or brightness out of range).
+	InvalidPayload(String),
+	/// An error surfaced by the underlying `hidapi` device.
+	Hid(hidapi::HidError),
+	/// An I/O error reading evdev input events during reactive lighting.
+	Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::DeviceNotFound => write!(f, "No supported Legion keyboard found"),
+			Error::InvalidPayload(msg) => write!(f, "{}", msg),
+			Error::Hid(err) => write!(f, "{}", err),
+			Error::Io(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<hidapi::HidError> for Error {
+	fn from(err: hidapi::HidError) -> Self {
+		Error::Hid(err)
+	}
+}
+
+impl From<std::io::Error> for Error {
+	fn from(err: std::io::Error) -> Self {
+		Error::Io(err)
+	}
+}
@@ -1,23 +1,131 @@
-#![feature(assert_matches)]
-#![feature(exclusive_range_pattern)]
-
 use hidapi::{HidApi, HidDevice};
 
 mod error;
-
 #[cfg(target_os = "linux")]
-const DEVICE_INFO_2021: (u16, u16, u16, u16) = (0x048d, 0xc965, 0, 0);
+mod reactive;
+
+/// Static capability descriptor for a supported Legion keyboard: the USB
+/// identifiers used for matching (with the OS-specific usage page/usage), a
+/// human-readable model name, and the per-model lighting limits.
+struct DeviceDescriptor {
+	vendor_id: u16,
+	product_id: u16,
+	usage_page: u16,
+	usage: u16,
+	model: &'static str,
+	speed_range: std::ops::RangeInclusive<u8>,
+	brightness_range: std::ops::RangeInclusive<u8>,
+	zone_count: u8,
+}
+
+impl DeviceDescriptor {
+	/// The 4-tuple matched against each `hidapi` device entry.
+	fn match_tuple(&self) -> (u16, u16, u16, u16) {
+		(self.vendor_id, self.product_id, self.usage_page, self.usage)
+	}
+}
+
 #[cfg(target_os = "linux")]
-const DEVICE_INFO_2020: (u16, u16, u16, u16) = (0x048d, 0xc955, 0, 0);
-#[cfg(target_os = "windows")]
-const DEVICE_INFO_2021: (u16, u16, u16, u16) = (0x048d, 0xc965, 0xff89, 0x00cc);
+const USAGE: (u16, u16) = (0, 0);
 #[cfg(target_os = "windows")]
-const DEVICE_INFO_2020: (u16, u16, u16, u16) = (0x048d, 0xc955, 0xff89, 0x00cc);
+const USAGE: (u16, u16) = (0xff89, 0x00cc);
+
+const DEVICES: &[DeviceDescriptor] = &[
+	DeviceDescriptor {
+		vendor_id: 0x048d,
+		product_id: 0xc965,
+		usage_page: USAGE.0,
+		usage: USAGE.1,
+		model: "Legion (2021)",
+		speed_range: 1..=4,
+		brightness_range: 1..=2,
+		zone_count: 4,
+	},
+	DeviceDescriptor {
+		vendor_id: 0x048d,
+		product_id: 0xc955,
+		usage_page: USAGE.0,
+		usage: USAGE.1,
+		model: "Legion (2020)",
+		speed_range: 1..=4,
+		brightness_range: 1..=2,
+		zone_count: 4,
+	},
+];
 
-const SPEED_RANGE: std::ops::RangeInclusive<u8> = 1..=4;
-const BRIGHTNESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+/// Firmware lighting effect written into `payload[2]`.
+///
+/// The controller multiplexes the animation mode on the command byte that
+/// `build_payload` used to hard-code to `0x01` (static). The speed byte only
+/// affects the animated effects; for `Static` and `Off` it is ignored by the
+/// firmware but still range-validated.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+	Static,
+	Breath,
+	Wave,
+	/// Continuously cycles the hue across all zones ("smooth").
+	Hue,
+	Off,
+}
+
+impl Effect {
+	fn opcode(self) -> u8 {
+		match self {
+			Effect::Static => 0x01,
+			Effect::Breath => 0x03,
+			Effect::Wave => 0x04,
+			Effect::Hue => 0x06,
+			Effect::Off => 0x00,
+		}
+	}
+}
+
+/// One of the four keyboard lighting regions, left to right. Each zone owns
+/// three consecutive bytes of `LightingState::rgb_values`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+	Left,
+	CenterLeft,
+	CenterRight,
+	Right,
+}
+
+impl Zone {
+	/// Zones ordered left to right, for gradient and iteration helpers.
+	const ALL: [Zone; 4] = [Zone::Left, Zone::CenterLeft, Zone::CenterRight, Zone::Right];
+
+	/// Byte offset of this zone's red channel within `rgb_values`.
+	fn offset(self) -> usize {
+		match self {
+			Zone::Left => 0,
+			Zone::CenterLeft => 3,
+			Zone::CenterRight => 6,
+			Zone::Right => 9,
+		}
+	}
+}
+
+/// Computes the 12-byte buffer for a left-to-right gradient from `from` to
+/// `to` across the four zones. Each channel of zone `i` is
+/// `from + (to - from) * i / 3`, rounded to the nearest byte.
+fn gradient(from: [u8; 3], to: [u8; 3]) -> [u8; 12] {
+	let mut rgb_values = [0; 12];
+	for (i, zone) in Zone::ALL.iter().enumerate() {
+		let offset = zone.offset();
+		for channel in 0..3 {
+			let f = i32::from(from[channel]);
+			let t = i32::from(to[channel]);
+			let num = (t - f) * i as i32;
+			rgb_values[offset + channel] = (f + (num + num.signum()) / 3) as u8;
+		}
+	}
+	rgb_values
+}
 
+#[derive(Clone)]
 pub struct LightingState {
+	effect: Effect,
 	speed: u8,
 	brightness: u8,
 	rgb_values: [u8; 12],
@@ -25,24 +133,35 @@ pub struct LightingState {
 
 pub struct Keyboard {
 	keyboard_hid: HidDevice,
+	device: &'static DeviceDescriptor,
 	current_state: LightingState,
 }
 
 #[allow(dead_code)]
 impl Keyboard {
-	fn build_payload(&self) -> Result<[u8; 33], &'static str> {
+	fn build_payload(&self) -> Result<[u8; 33], error::Error> {
 		let keyboard_state = &self.current_state;
 
-		if !SPEED_RANGE.contains(&keyboard_state.speed) {
-			return Err("Speed is outside valid range (1-4)");
+		let speed_range = &self.device.speed_range;
+		if !speed_range.contains(&keyboard_state.speed) {
+			return Err(error::Error::InvalidPayload(format!(
+				"Speed is outside valid range ({}-{})",
+				speed_range.start(),
+				speed_range.end()
+			)));
 		}
-		if !BRIGHTNESS_RANGE.contains(&keyboard_state.brightness) {
-			return Err("Brightness is outside valid range (1-2)");
+		let brightness_range = &self.device.brightness_range;
+		if !brightness_range.contains(&keyboard_state.brightness) {
+			return Err(error::Error::InvalidPayload(format!(
+				"Brightness is outside valid range ({}-{})",
+				brightness_range.start(),
+				brightness_range.end()
+			)));
 		}
 		let mut payload: [u8; 33] = [0; 33];
 		payload[0] = 0xcc;
 		payload[1] = 0x16;
-		payload[2] = 0x01;
+		payload[2] = keyboard_state.effect.opcode();
 		payload[3] = keyboard_state.speed;
 		payload[4] = keyboard_state.brightness;
 		payload[5..(5 + 12)].copy_from_slice(&keyboard_state.rgb_values);
@@ -50,49 +169,99 @@ impl Keyboard {
 		Ok(payload)
 	}
 
-	pub fn refresh(&mut self) {
-		let payload = match self.build_payload() {
-			Ok(payload) => payload,
-			Err(err) => panic!("Payload build error: {}", err),
-		};
-		match self.keyboard_hid.send_feature_report(&payload) {
-			Ok(_keyboard_hid) => {}
-			Err(err) => panic!("Sending feature report failed: {}", err),
-		};
+	pub fn refresh(&mut self) -> Result<(), error::Error> {
+		let payload = self.build_payload()?;
+		self.keyboard_hid.send_feature_report(&payload)?;
+		Ok(())
 	}
 
-	pub fn set_speed(&mut self, speed: u8) {
-		let speed = speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end());
+	pub fn set_speed(&mut self, speed: u8) -> Result<(), error::Error> {
+		let range = &self.device.speed_range;
+		let speed = speed.clamp(*range.start(), *range.end());
 		self.current_state.speed = speed;
-		self.refresh();
+		self.refresh()
 	}
 
-	pub fn set_brightness(&mut self, brightness: u8) {
-		let brightness = brightness.clamp(*BRIGHTNESS_RANGE.start(), *BRIGHTNESS_RANGE.end());
+	pub fn set_brightness(&mut self, brightness: u8) -> Result<(), error::Error> {
+		let range = &self.device.brightness_range;
+		let brightness = brightness.clamp(*range.start(), *range.end());
 		self.current_state.brightness = brightness;
-		self.refresh();
+		self.refresh()
+	}
+
+	/// Human-readable name of the detected model.
+	pub fn model(&self) -> &'static str {
+		self.device.model
+	}
+
+	/// Number of independently addressable lighting zones on this model.
+	pub fn zone_count(&self) -> u8 {
+		self.device.zone_count
+	}
+
+	pub fn set_effect(&mut self, effect: Effect) -> Result<(), error::Error> {
+		self.current_state.effect = effect;
+		self.refresh()
 	}
 
-	pub fn set_colors_to(&mut self, new_values: &[u8; 12]) {
+	pub fn set_colors_to(&mut self, new_values: &[u8; 12]) -> Result<(), error::Error> {
 		self.current_state.rgb_values = *new_values;
-		self.refresh();
+		self.refresh()
+	}
+
+	pub fn set_zone_color(&mut self, zone: Zone, rgb: [u8; 3]) -> Result<(), error::Error> {
+		let offset = zone.offset();
+		self.current_state.rgb_values[offset..offset + 3].copy_from_slice(&rgb);
+		self.refresh()
+	}
+
+	pub fn zone_color(&self, zone: Zone) -> [u8; 3] {
+		let offset = zone.offset();
+		let mut rgb = [0; 3];
+		rgb.copy_from_slice(&self.current_state.rgb_values[offset..offset + 3]);
+		rgb
+	}
+
+	/// Paints a left-to-right gradient across the four zones by linear
+	/// interpolation in RGB space: for zone `i`,
+	/// `channel = from + (to - from) * i / 3`, rounded to the nearest byte.
+	pub fn set_gradient(&mut self, from: [u8; 3], to: [u8; 3]) -> Result<(), error::Error> {
+		self.current_state.rgb_values = gradient(from, to);
+		self.refresh()
+	}
+
+	/// Returns a copy of the current lighting state so it can be reapplied
+	/// later with [`Keyboard::restore`].
+	pub fn snapshot(&self) -> LightingState {
+		self.current_state.clone()
+	}
+
+	/// Reapplies a previously captured lighting state, e.g. after a
+	/// suspend/resume cycle where the device transiently disappeared.
+	pub fn restore(&mut self, state: LightingState) -> Result<(), error::Error> {
+		self.current_state = state;
+		self.refresh()
 	}
 }
 
 pub fn get_keyboard() -> Result<Keyboard, error::Error> {
 	let api: HidApi = HidApi::new()?;
 
-	let info = api
+	let (info, device) = api
 		.device_list()
-		.find(|d| {
+		.find_map(|d| {
 			let info_tuple = (d.vendor_id(), d.product_id(), d.usage_page(), d.usage());
-			info_tuple == DEVICE_INFO_2021 || info_tuple == DEVICE_INFO_2020
+			DEVICES
+				.iter()
+				.find(|descriptor| descriptor.match_tuple() == info_tuple)
+				.map(|descriptor| (d, descriptor))
 		})
 		.ok_or(error::Error::DeviceNotFound)?;
 
 	let keyboard_hid: HidDevice = info.open_device(&api)?;
 
 	let current_state: LightingState = LightingState {
+		effect: Effect::Static,
 		speed: 1,
 		brightness: 1,
 		rgb_values: [0; 12],
@@ -100,9 +269,37 @@ pub fn get_keyboard() -> Result<Keyboard, error::Error> {
 
 	let mut keyboard = Keyboard {
 		keyboard_hid,
+		device,
 		current_state,
 	};
 
-	keyboard.refresh();
+	keyboard.refresh()?;
 	Ok(keyboard)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn gradient_endpoints_land_on_from_and_to() {
+		let values = gradient([10, 20, 30], [250, 240, 230]);
+		assert_eq!(&values[0..3], &[10, 20, 30]);
+		assert_eq!(&values[9..12], &[250, 240, 230]);
+	}
+
+	#[test]
+	fn gradient_rounds_evenly_spaced_zones() {
+		// 0..=255 over four zones rounds to 0, 85, 170, 255 per channel.
+		let values = gradient([0, 0, 0], [255, 255, 255]);
+		assert_eq!(values, [0, 0, 0, 85, 85, 85, 170, 170, 170, 255, 255, 255]);
+	}
+
+	#[test]
+	fn zone_offsets_are_contiguous() {
+		assert_eq!(Zone::Left.offset(), 0);
+		assert_eq!(Zone::CenterLeft.offset(), 3);
+		assert_eq!(Zone::CenterRight.offset(), 6);
+		assert_eq!(Zone::Right.offset(), 9);
+	}
+}
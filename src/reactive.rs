@@ -0,0 +1,165 @@
+//! Software-driven reactive lighting.
+//!
+//! The hardware exposes only four static zones plus a firmware speed
+//! parameter, so per-keypress reaction is synthesized on the host: we read raw
+//! `EV_KEY` events from the keyboard's evdev node, flash the zone a pressed key
+//! belongs to up to an accent color, then linearly fade it back to the base
+//! color over a fixed number of frames. The resulting RGB buffer is pushed with
+//! `Keyboard::set_colors_to` at a clamped tick rate so we never flood
+//! `send_feature_report`.
+
+use std::time::{Duration, Instant};
+
+use evdev::{Device, EventSummary, KeyCode};
+
+use crate::Keyboard;
+
+/// Number of frames a zone takes to decay from the accent color back to base.
+const DECAY_FRAMES: u16 = 18;
+/// Lower bound on the interval between feature reports (~60 Hz).
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Maps an evdev key to one of the four keyboard zones (left to right), or
+/// `None` for keys we don't light.
+fn zone_of(key: KeyCode) -> Option<usize> {
+	// Linux input-event-codes are numbered row by row, not left to right, so a
+	// zone can't be a contiguous code range: we group the keys of every row
+	// into their left-to-right quarter explicitly (US layout keycodes).
+	match key.code() {
+		// Esc/grave, Tab, Caps, LShift, LCtrl, 1-2, QW, AS, ZX
+		1 | 41 | 15 | 58 | 42 | 29 | 2 | 3 | 16 | 17 | 30 | 31 | 44 | 45 => Some(0),
+		// 3-5, ERT, DFG, CVB
+		4 | 5 | 6 | 18 | 19 | 20 | 32 | 33 | 34 | 46 | 47 | 48 => Some(1),
+		// 6-8, YUI, HJK, NM
+		7 | 8 | 9 | 21 | 22 | 23 | 35 | 36 | 37 | 49 | 50 => Some(2),
+		// 9-0 and right edge: OP brackets, L ; ', comma/dot/slash, Enter, RShift
+		10 | 11 | 12 | 13 | 14 | 24 | 25 | 26 | 27 | 28 | 38 | 39 | 40 | 43 | 51 | 52 | 53 | 54 => Some(3),
+		_ => None,
+	}
+}
+
+/// Returns the keyboard input devices, excluding mice/touchpads and other
+/// nodes that don't advertise the alphabetic key range.
+fn keyboard_devices() -> Vec<Device> {
+	evdev::enumerate()
+		.map(|(_, device)| device)
+		.filter(|device| {
+			device
+				.supported_keys()
+				.is_some_and(|keys| keys.contains(KeyCode::KEY_A) && keys.contains(KeyCode::KEY_Z))
+		})
+		.collect()
+}
+
+/// Blends each channel from `base` towards `accent` by `intensity` (0..=255)
+/// and writes the 12-byte RGB buffer.
+fn blend(base: &[u8; 12], accent: &[u8; 12], intensity: &[u8; 4]) -> [u8; 12] {
+	let mut out = *base;
+	for (zone, &level) in intensity.iter().enumerate() {
+		let t = i32::from(level);
+		for channel in 0..3 {
+			let i = zone * 3 + channel;
+			let b = i32::from(base[i]);
+			let a = i32::from(accent[i]);
+			// Linear interpolation towards the accent color; the result lies
+			// between base and accent, so it fits in a byte.
+			out[i] = (b + (a - b) * t / 255) as u8;
+		}
+	}
+	out
+}
+
+impl Keyboard {
+	/// Runs the reactive lighting loop until an input device disappears or a
+	/// feature report fails. Each keypress flashes its zone to `accent`; idle
+	/// zones decay back to `base`.
+	pub fn run_reactive(&mut self, base: [u8; 12], accent: [u8; 12]) -> Result<(), crate::error::Error> {
+		let mut devices = keyboard_devices();
+		// Read without blocking so the decay clock below ticks on schedule even
+		// when no keys are pressed, and so we service every device each frame
+		// instead of parking inside the first one's fetch.
+		for device in &mut devices {
+			device.set_nonblocking(true)?;
+		}
+
+		let mut intensity: [u8; 4] = [0; 4];
+		let step = (255 / DECAY_FRAMES) as u8;
+
+		self.set_colors_to(&base)?;
+		let mut current = base;
+
+		loop {
+			let frame_start = Instant::now();
+
+			// Decay before reading input so a zone pressed this frame is shown at
+			// full accent.
+			for level in &mut intensity {
+				*level = level.saturating_sub(step);
+			}
+
+			for device in &mut devices {
+				match device.fetch_events() {
+					Ok(events) => {
+						for event in events {
+							// value 1 = press, 2 = autorepeat, 0 = release.
+							if let EventSummary::Key(_, key, value) = event.destructure() {
+								if value != 0 {
+									if let Some(zone) = zone_of(key) {
+										intensity[zone] = 255;
+									}
+								}
+							}
+						}
+					}
+					// No events queued on this device this frame.
+					Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+					Err(err) => return Err(err.into()),
+				}
+			}
+
+			// Only push a feature report when the buffer actually changed.
+			let values = blend(&base, &accent, &intensity);
+			if values != current {
+				self.set_colors_to(&values)?;
+				current = values;
+			}
+
+			if let Some(remaining) = FRAME_INTERVAL.checked_sub(frame_start.elapsed()) {
+				std::thread::sleep(remaining);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn blend_endpoints_are_base_and_accent() {
+		let base = [10; 12];
+		let accent = [200; 12];
+		assert_eq!(blend(&base, &accent, &[0; 4]), base);
+		assert_eq!(blend(&base, &accent, &[255; 4]), accent);
+	}
+
+	#[test]
+	fn blend_midpoint_interpolates_per_zone() {
+		let base = [0; 12];
+		let accent = [100; 12];
+		// Only zone 1 is at half intensity; the rest stay at base.
+		let out = blend(&base, &accent, &[0, 128, 0, 0]);
+		assert_eq!(&out[0..3], &[0, 0, 0]);
+		assert_eq!(&out[3..6], &[50, 50, 50]);
+		assert_eq!(&out[6..9], &[0, 0, 0]);
+	}
+
+	#[test]
+	fn zone_of_spans_left_to_right() {
+		assert_eq!(zone_of(KeyCode::KEY_Q), Some(0));
+		assert_eq!(zone_of(KeyCode::KEY_R), Some(1));
+		assert_eq!(zone_of(KeyCode::KEY_U), Some(2));
+		assert_eq!(zone_of(KeyCode::KEY_P), Some(3));
+		assert_eq!(zone_of(KeyCode::KEY_COMMA), Some(3));
+	}
+}